@@ -1,4 +1,15 @@
 //! This is documentation for the `procset` crate.
+extern crate num_traits;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+
 pub mod interval_set;
+#[cfg(feature = "serde")]
+pub mod serde_impl;
 
 pub use interval_set::*;
@@ -0,0 +1,211 @@
+//! `Serialize` and `Deserialize` implementations, available with the `serde` feature.
+//!
+//! An `Interval` is written as a `[begin, end]` pair, and an `IntervalSet` as its string form
+//! (`"0-3 7"`), as expected by Batsim messages.
+//! The `as_string` and `as_pairs` modules can be given to `#[serde(with = ...)]` to choose the
+//! representation of an `IntervalSet` field.
+//!
+//! # Example
+//!
+//! ```
+//! #[macro_use]
+//! extern crate serde_derive;
+//! extern crate serde_json;
+//! extern crate interval_set;
+//!
+//! use interval_set::interval_set::{IntervalSet, ToIntervalSet};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Job {
+//!     #[serde(with = "interval_set::serde_impl::as_pairs")]
+//!     alloc: IntervalSet,
+//! }
+//!
+//! fn main() {
+//!     let job = Job { alloc: String::from("0-3 7").to_interval_set() };
+//!     assert_eq!(serde_json::to_string(&job).unwrap(), r#"{"alloc":[[0,3],[7,7]]}"#);
+//! }
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use interval_set::{Discrete, Interval, IntervalSet};
+
+impl<T: Discrete + Serialize> Serialize for Interval<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.get_inf(), self.get_sup()).serialize(serializer)
+    }
+}
+
+impl<'de, T: Discrete + Deserialize<'de>> Deserialize<'de> for Interval<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Interval<T>, D::Error> {
+        let (begin, end) = <(T, T)>::deserialize(deserializer)?;
+        if begin > end {
+            return Err(de::Error::custom(format!("Invalid interval: {:?}-{:?}", begin, end)));
+        }
+        Ok(Interval::new(begin, end))
+    }
+}
+
+impl<T: Discrete + fmt::Display> Serialize for IntervalSet<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de, T: Discrete + FromStr + fmt::Display> Deserialize<'de> for IntervalSet<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<IntervalSet<T>, D::Error> {
+        deserializer.deserialize_str(IntervalSetStrVisitor(PhantomData))
+    }
+}
+
+/// Parse an interval set from its string form.
+struct IntervalSetStrVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Discrete + FromStr + fmt::Display> Visitor<'de> for IntervalSetStrVisitor<T> {
+    type Value = IntervalSet<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an interval set such as \"0-3 7\"")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<IntervalSet<T>, E> {
+        value.parse().map_err(E::custom)
+    }
+}
+
+/// Write an `IntervalSet` as its string form, `"0-3 7"`. This is the default representation.
+pub mod as_string {
+    use std::fmt;
+    use std::str::FromStr;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use interval_set::{Discrete, IntervalSet};
+
+    pub fn serialize<T, S>(set: &IntervalSet<T>, serializer: S) -> Result<S::Ok, S::Error>
+        where T: Discrete + fmt::Display,
+              S: Serializer
+    {
+        set.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<IntervalSet<T>, D::Error>
+        where T: Discrete + FromStr + fmt::Display,
+              D: Deserializer<'de>
+    {
+        IntervalSet::deserialize(deserializer)
+    }
+}
+
+/// Write an `IntervalSet` as a list of `[begin, end]` pairs, `[[0, 3], [7, 7]]`.
+/// The pairs are read in any order, and may overlap.
+pub mod as_pairs {
+    use std::marker::PhantomData;
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use interval_set::{Discrete, IntervalSet};
+    use super::IntervalSetPairsVisitor;
+
+    pub fn serialize<T, S>(set: &IntervalSet<T>, serializer: S) -> Result<S::Ok, S::Error>
+        where T: Discrete + Serialize,
+              S: Serializer
+    {
+        serializer.collect_seq(set.iter())
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<IntervalSet<T>, D::Error>
+        where T: Discrete + Deserialize<'de>,
+              D: Deserializer<'de>
+    {
+        deserializer.deserialize_seq(IntervalSetPairsVisitor(PhantomData))
+    }
+}
+
+/// Read an interval set from a sequence of `[begin, end]` pairs.
+struct IntervalSetPairsVisitor<T>(PhantomData<T>);
+
+impl<'de, T: Discrete + Deserialize<'de>> Visitor<'de> for IntervalSetPairsVisitor<T> {
+    type Value = IntervalSet<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list of [begin, end] pairs")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<IntervalSet<T>, A::Error> {
+        let mut intervals: Vec<Interval<T>> = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(intv) = seq.next_element()? {
+            intervals.push(intv);
+        }
+        Ok(intervals.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+
+    use interval_set::{Interval, IntervalSet, ToIntervalSet};
+
+    #[test]
+    fn test_serialize() {
+        let a = String::from("0-3 7 10-12").to_interval_set();
+        assert_eq!(serde_json::to_string(&a).unwrap(), "\"0-3 7 10-12\"");
+        assert_eq!(serde_json::to_string(&IntervalSet::<u32>::empty()).unwrap(), "\"\"");
+        assert_eq!(serde_json::to_string(&Interval::new(2, 5)).unwrap(), "[2,5]");
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let a: IntervalSet = serde_json::from_str("\"7 0-3 2-5\"").unwrap();
+        assert_eq!(a, String::from("0-5 7").to_interval_set());
+        let b: IntervalSet<u64> = serde_json::from_str("\"5000000000-5000000001\"").unwrap();
+        assert_eq!(b.to_string(), "5000000000-5000000001");
+        let intv: Interval = serde_json::from_str("[2, 5]").unwrap();
+        assert_eq!(intv, Interval::new(2, 5));
+
+        assert!(serde_json::from_str::<IntervalSet>("\"3-1\"").is_err());
+        assert!(serde_json::from_str::<IntervalSet>("[[0, 3]]").is_err());
+        assert!(serde_json::from_str::<Interval>("[5, 2]").is_err());
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Job {
+        #[serde(with = "::serde_impl::as_string")]
+        alloc: IntervalSet,
+        #[serde(with = "::serde_impl::as_pairs")]
+        reserved: IntervalSet<u64>,
+    }
+
+    #[test]
+    fn test_with_representations() {
+        let job = Job {
+            alloc: String::from("0-3 7").to_interval_set(),
+            reserved: vec![Interval::new(10u64, 12), Interval::new(20, 20)].into_iter().collect(),
+        };
+        let json = serde_json::to_string(&job).unwrap();
+        assert_eq!(json, r#"{"alloc":"0-3 7","reserved":[[10,12],[20,20]]}"#);
+        assert_eq!(serde_json::from_str::<Job>(&json).unwrap(), job);
+
+        let job: Job = serde_json::from_str(r#"{"alloc":"","reserved":[[20,20],[5,11],[10,12]]}"#)
+            .unwrap();
+        assert!(job.alloc.is_empty());
+        assert_eq!(job.reserved.to_string(), "5-12 20");
+
+        assert!(serde_json::from_str::<Job>(r#"{"alloc":"","reserved":[[2,1]]}"#).is_err());
+        assert!(serde_json::from_str::<Job>(r#"{"alloc":"","reserved":"0-3"}"#).is_err());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let a = String::from("0-3 7 10-12").to_interval_set();
+        let json = serde_json::to_string(&a).unwrap();
+        assert_eq!(serde_json::from_str::<IntervalSet>(&json).unwrap(), a);
+    }
+}